@@ -1,10 +1,209 @@
-use ethers::types::{Address, U256};
+use ethers::providers::{Middleware, MiddlewareError};
+use ethers::types::{Address, BlockId, Bytes, NameOrAddress, TransactionRequest, U256};
 use serde::{Deserialize, Serialize};
 use state_fold_types::ethers;
 use std::sync::Arc;
 
+/// Current on-disk layout version of [`EpochInitialState`]. Bump this and add
+/// a historical shadow struct plus an upgrade arm in [`migrate`] whenever the
+/// layout changes, so previously checkpointed epoch state keeps loading.
+pub const EPOCH_INITIAL_STATE_VERSION: u16 = 1;
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct EpochInitialState {
+    /// Layout version this value was constructed with; see [`migrate`].
+    pub version: u16,
+
     pub dapp_contract_address: Arc<Address>,
     pub epoch_number: U256,
-}
\ No newline at end of file
+
+    /// L1 execution-layer block number that was current when this epoch opened.
+    pub l1_block_number: U256,
+
+    /// Parent beacon block root (EIP-4788) live at `l1_block_number`, if the
+    /// beacon roots contract had one recorded for the epoch's timestamp.
+    pub beacon_block_root: Option<[u8; 32]>,
+}
+
+impl EpochInitialState {
+    /// Builds an `EpochInitialState`, looking up the EIP-4788 parent beacon
+    /// block root that was live at `l1_block_number` by querying the beacon
+    /// roots contract with the block's timestamp.
+    pub async fn new<M: Middleware>(
+        provider: Arc<M>,
+        dapp_contract_address: Arc<Address>,
+        epoch_number: U256,
+        l1_block_number: U256,
+    ) -> Result<Self, EpochInitialStateError<M::Error>>
+    where
+        M::Error: MiddlewareError,
+    {
+        let block = provider
+            .get_block(BlockId::from(l1_block_number.as_u64()))
+            .await?
+            .ok_or(EpochInitialStateError::BlockNotFound { l1_block_number })?;
+
+        let beacon_block_root =
+            fetch_beacon_root(provider.as_ref(), block.timestamp).await?;
+
+        Ok(Self {
+            version: EPOCH_INITIAL_STATE_VERSION,
+            dapp_contract_address,
+            epoch_number,
+            l1_block_number,
+            beacon_block_root,
+        })
+    }
+}
+
+/// Errors that can occur while anchoring an `EpochInitialState` to an L1
+/// block and its EIP-4788 beacon root.
+#[derive(Debug)]
+pub enum EpochInitialStateError<E> {
+    /// The underlying provider call failed.
+    Provider(E),
+    /// `l1_block_number` isn't known to the provider (pruned, ahead of its
+    /// view, or lost to a reorg racing the caller).
+    BlockNotFound { l1_block_number: U256 },
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for EpochInitialStateError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Provider(e) => write!(f, "{e}"),
+            Self::BlockNotFound { l1_block_number } => {
+                write!(f, "l1 block {l1_block_number} not found")
+            }
+        }
+    }
+}
+
+impl<E: std::fmt::Debug + std::fmt::Display> std::error::Error for EpochInitialStateError<E> {}
+
+impl<E> From<E> for EpochInitialStateError<E> {
+    fn from(e: E) -> Self {
+        Self::Provider(e)
+    }
+}
+
+/// Layout that predates the L1 block anchor and beacon root fields: the
+/// original `EpochInitialState` shipped with no `version` tag at all.
+#[derive(Deserialize)]
+struct EpochInitialStateV0 {
+    dapp_contract_address: Arc<Address>,
+    epoch_number: U256,
+}
+
+/// Just enough of the payload to read the `version` discriminant without
+/// committing to the rest of the current layout.
+#[derive(Deserialize)]
+struct VersionProbe {
+    #[serde(default)]
+    version: u16,
+}
+
+/// Deserializes a persisted `EpochInitialState`, upgrading older on-disk
+/// layouts to the current representation with sensible defaults. Payloads
+/// from before versioning was introduced (no `version` field) are treated as
+/// v0 and filled in with a zero L1 block anchor and no beacon root.
+pub fn migrate(raw: &[u8]) -> Result<EpochInitialState, serde_json::Error> {
+    let probe: VersionProbe = serde_json::from_slice(raw)?;
+
+    if probe.version >= EPOCH_INITIAL_STATE_VERSION {
+        return serde_json::from_slice(raw);
+    }
+
+    let v0: EpochInitialStateV0 = serde_json::from_slice(raw)?;
+    Ok(EpochInitialState {
+        version: EPOCH_INITIAL_STATE_VERSION,
+        dapp_contract_address: v0.dapp_contract_address,
+        epoch_number: v0.epoch_number,
+        l1_block_number: U256::zero(),
+        beacon_block_root: None,
+    })
+}
+
+/// Calls the EIP-4788 beacon roots contract with `timestamp` ABI-encoded as a
+/// 32-byte big-endian word, returning the beacon root it replies with. A
+/// contract-level revert or empty return legitimately means no root is
+/// available for that timestamp; a provider-level error (transport failure,
+/// timeout, rate limit) is propagated instead of being mistaken for that.
+async fn fetch_beacon_root<M: Middleware>(
+    provider: &M,
+    timestamp: U256,
+) -> Result<Option<[u8; 32]>, EpochInitialStateError<M::Error>>
+where
+    M::Error: MiddlewareError,
+{
+    let mut calldata = [0u8; 32];
+    timestamp.to_big_endian(&mut calldata);
+
+    let tx = TransactionRequest::new()
+        .to(NameOrAddress::Address(beacon_roots_address()))
+        .data(Bytes::from(calldata.to_vec()));
+
+    let result = match provider.call(&tx.into(), None).await {
+        Ok(bytes) => bytes,
+        // The call round-tripped and the node replied with a JSON-RPC error
+        // (e.g. the beacon roots contract reverted) — no root for this
+        // timestamp. Anything else (the call never got a response) is a
+        // provider-level failure and must not be mistaken for that.
+        Err(e) if e.as_error_response().is_some() => return Ok(None),
+        Err(e) => return Err(EpochInitialStateError::Provider(e)),
+    };
+
+    if result.len() != 32 {
+        return Ok(None);
+    }
+
+    let mut root = [0u8; 32];
+    root.copy_from_slice(&result);
+    Ok(Some(root))
+}
+
+/// `0x000F3df6D732807Ef1319fB7B8bB8522d0Beac02`, the well-known address of
+/// the EIP-4788 beacon roots contract.
+fn beacon_roots_address() -> Address {
+    "000F3df6D732807Ef1319fB7B8bB8522d0Beac02"
+        .parse()
+        .expect("valid beacon roots address")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> EpochInitialState {
+        EpochInitialState {
+            version: EPOCH_INITIAL_STATE_VERSION,
+            dapp_contract_address: Arc::new(Address::repeat_byte(0x42)),
+            epoch_number: U256::from(7),
+            l1_block_number: U256::from(123456),
+            beacon_block_root: Some([0xab; 32]),
+        }
+    }
+
+    #[test]
+    fn round_trips_current_layout() {
+        let state = sample();
+        let raw = serde_json::to_vec(&state).unwrap();
+        assert_eq!(migrate(&raw).unwrap(), state);
+    }
+
+    #[test]
+    fn migrates_v0_layout_with_defaults() {
+        let raw = serde_json::json!({
+            "dapp_contract_address": Arc::new(Address::repeat_byte(0x42)),
+            "epoch_number": U256::from(7),
+        })
+        .to_string()
+        .into_bytes();
+
+        let migrated = migrate(&raw).unwrap();
+        assert_eq!(migrated.version, EPOCH_INITIAL_STATE_VERSION);
+        assert_eq!(migrated.dapp_contract_address, Arc::new(Address::repeat_byte(0x42)));
+        assert_eq!(migrated.epoch_number, U256::from(7));
+        assert_eq!(migrated.l1_block_number, U256::zero());
+        assert_eq!(migrated.beacon_block_root, None);
+    }
+}
@@ -0,0 +1,250 @@
+use async_stream::stream;
+use ethers::providers::{Middleware, PubsubClient};
+use ethers::types::{Address, Filter, Log, U256};
+use futures::stream::{Stream, StreamExt};
+use state_fold_types::ethers;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::EpochInitialState;
+
+/// Human-readable signature of the DApp's epoch-advancing event
+/// (`event EpochAdvance(uint256 indexed epochNumber)`). `Filter::event` hashes
+/// this itself, so it must stay the Solidity signature, not a precomputed
+/// topic hash.
+const EPOCH_ADVANCE_EVENT_SIGNATURE: &str = "EpochAdvance(uint256)";
+
+/// Initial delay before retrying a failed backfill or subscription attempt,
+/// doubled on each consecutive failure up to `MAX_RECONNECT_BACKOFF`.
+const MIN_RECONNECT_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(30);
+
+/// The last epoch this subscription emitted, together with the L1 block it
+/// was seen at, so a reconnect only has to backfill what happened since.
+#[derive(Clone, Copy)]
+struct LastSeen {
+    epoch_number: U256,
+    l1_block_number: U256,
+}
+
+/// Subscribes on `provider` to the epoch-advancing event logs of the DApp at
+/// `dapp_contract_address` and yields an [`EpochInitialState`] every time
+/// that event is emitted. Requires a provider that supports push subscriptions (WebSocket or
+/// IPC); polling providers are not accepted.
+///
+/// If the underlying socket drops, the subscription is re-established and any
+/// epochs that advanced while disconnected are backfilled via `eth_getLogs`
+/// and re-emitted in order before live events resume. Failed backfill or
+/// resubscribe attempts are retried with exponential backoff rather than
+/// busy-looping against the provider.
+pub fn subscribe_epochs<M>(
+    provider: Arc<M>,
+    dapp_contract_address: Arc<Address>,
+) -> impl Stream<Item = EpochInitialState>
+where
+    M: Middleware + 'static,
+    M::Provider: PubsubClient,
+{
+    stream! {
+        let mut last_seen: Option<LastSeen> = None;
+        let mut backoff = MIN_RECONNECT_BACKOFF;
+
+        loop {
+            match backfill(provider.as_ref(), &dapp_contract_address, last_seen).await {
+                Ok(backfilled) => {
+                    for epoch in backfilled {
+                        last_seen = Some(LastSeen {
+                            epoch_number: epoch.epoch_number,
+                            l1_block_number: epoch.l1_block_number,
+                        });
+                        yield epoch;
+                    }
+                }
+                Err(error) => {
+                    tracing::warn!(
+                        ?error,
+                        dapp_contract_address = ?dapp_contract_address,
+                        backoff_ms = backoff.as_millis() as u64,
+                        "epoch backfill failed, retrying after backoff",
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_RECONNECT_BACKOFF);
+                    continue;
+                }
+            }
+
+            let filter = epoch_advance_filter(&dapp_contract_address);
+            let mut sub = match provider.subscribe_logs(&filter).await {
+                Ok(sub) => {
+                    backoff = MIN_RECONNECT_BACKOFF;
+                    sub
+                }
+                Err(error) => {
+                    tracing::warn!(
+                        ?error,
+                        dapp_contract_address = ?dapp_contract_address,
+                        backoff_ms = backoff.as_millis() as u64,
+                        "epoch subscription failed, retrying after backoff",
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_RECONNECT_BACKOFF);
+                    continue;
+                }
+            };
+
+            // Keep pulling from this same subscription for as long as it
+            // stays open, so we neither tear down the server-side
+            // subscription nor leave a gap between `next()` calls.
+            while let Some(log) = sub.next().await {
+                let epoch_number = log
+                    .topics
+                    .get(1)
+                    .map(|t| U256::from_big_endian(t.as_bytes()))
+                    .unwrap_or_default();
+                let l1_block_number = log
+                    .block_number
+                    .map(|n| n.as_u64().into())
+                    .unwrap_or_default();
+
+                last_seen = Some(LastSeen {
+                    epoch_number,
+                    l1_block_number,
+                });
+
+                yield EpochInitialState {
+                    version: crate::EPOCH_INITIAL_STATE_VERSION,
+                    dapp_contract_address: dapp_contract_address.clone(),
+                    epoch_number,
+                    l1_block_number,
+                    beacon_block_root: None,
+                };
+            }
+
+            // The socket closed; loop back around to reconnect and backfill
+            // from where we left off.
+        }
+    }
+}
+
+/// Re-derives any epochs that advanced at or after `last_seen` by replaying
+/// past logs, so a reconnect never silently drops an epoch advance. Bounded
+/// to start from `last_seen.l1_block_number` itself (not `+ 1`) in case the
+/// socket dropped mid-block after only some of that block's logs were
+/// delivered; `logs_to_epochs` dedupes against `last_seen.epoch_number` so
+/// the already-emitted epoch isn't yielded twice.
+async fn backfill<M: Middleware>(
+    provider: &M,
+    dapp_contract_address: &Address,
+    last_seen: Option<LastSeen>,
+) -> Result<Vec<EpochInitialState>, M::Error> {
+    let mut filter = epoch_advance_filter(dapp_contract_address);
+    if let Some(last_seen) = last_seen {
+        filter = filter.from_block(last_seen.l1_block_number.as_u64());
+    }
+
+    let logs = provider.get_logs(&filter).await?;
+    Ok(logs_to_epochs(
+        logs,
+        dapp_contract_address,
+        last_seen.map(|s| s.epoch_number),
+    ))
+}
+
+/// Turns raw `EpochAdvance` logs into `EpochInitialState`s, dropping anything
+/// at or before `last_epoch` and returning the rest sorted by epoch number.
+/// Pulled out of `backfill` so the dedup/sort logic can be tested without a
+/// live provider.
+fn logs_to_epochs(
+    logs: Vec<Log>,
+    dapp_contract_address: &Address,
+    last_epoch: Option<U256>,
+) -> Vec<EpochInitialState> {
+    let mut epochs: Vec<EpochInitialState> = logs
+        .into_iter()
+        .map(|log| {
+            let epoch_number = log
+                .topics
+                .get(1)
+                .map(|t| U256::from_big_endian(t.as_bytes()))
+                .unwrap_or_default();
+
+            EpochInitialState {
+                version: crate::EPOCH_INITIAL_STATE_VERSION,
+                dapp_contract_address: Arc::new(*dapp_contract_address),
+                epoch_number,
+                l1_block_number: log
+                    .block_number
+                    .map(|n| n.as_u64().into())
+                    .unwrap_or_default(),
+                beacon_block_root: None,
+            }
+        })
+        .filter(|e| match last_epoch {
+            Some(last) => e.epoch_number > last,
+            None => true,
+        })
+        .collect();
+
+    epochs.sort_by_key(|e| e.epoch_number);
+    epochs
+}
+
+fn epoch_advance_filter(dapp_contract_address: &Address) -> Filter {
+    Filter::new()
+        .address(*dapp_contract_address)
+        .event(EPOCH_ADVANCE_EVENT_SIGNATURE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::H256;
+
+    fn log_for_epoch(epoch_number: u64, block_number: u64) -> Log {
+        let mut topics = vec![H256::zero(); 2];
+        topics[1] = H256::from_low_u64_be(epoch_number);
+
+        Log {
+            topics,
+            block_number: Some(block_number.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn drops_epochs_at_or_before_last_seen_and_sorts_the_rest() {
+        let dapp = Address::repeat_byte(0x7);
+        let logs = vec![
+            log_for_epoch(5, 100),
+            log_for_epoch(3, 90),
+            log_for_epoch(7, 110),
+        ];
+
+        let epochs = logs_to_epochs(logs, &dapp, Some(U256::from(3)));
+
+        let numbers: Vec<U256> = epochs.iter().map(|e| e.epoch_number).collect();
+        assert_eq!(numbers, vec![U256::from(5), U256::from(7)]);
+        assert!(epochs.iter().all(|e| *e.dapp_contract_address == dapp));
+    }
+
+    #[test]
+    fn keeps_everything_when_nothing_was_seen_yet() {
+        let dapp = Address::repeat_byte(0x7);
+        let logs = vec![log_for_epoch(1, 10), log_for_epoch(0, 5)];
+
+        let epochs = logs_to_epochs(logs, &dapp, None);
+
+        let numbers: Vec<U256> = epochs.iter().map(|e| e.epoch_number).collect();
+        assert_eq!(numbers, vec![U256::from(0), U256::from(1)]);
+    }
+
+    #[test]
+    fn drops_a_repeat_of_the_last_seen_epoch() {
+        let dapp = Address::repeat_byte(0x7);
+        let logs = vec![log_for_epoch(4, 100), log_for_epoch(4, 100)];
+
+        let epochs = logs_to_epochs(logs, &dapp, Some(U256::from(4)));
+
+        assert!(epochs.is_empty());
+    }
+}
@@ -0,0 +1,12 @@
+mod epoch_initial_state;
+mod epoch_subscription;
+mod multi_dapp_epoch_state;
+
+pub use epoch_initial_state::{
+    migrate, EpochInitialState, EpochInitialStateError, EPOCH_INITIAL_STATE_VERSION,
+};
+pub use epoch_subscription::subscribe_epochs;
+pub use multi_dapp_epoch_state::{
+    migrate as migrate_multi_dapp_epoch_state, MultiDappEpochState,
+    MULTI_DAPP_EPOCH_STATE_VERSION,
+};
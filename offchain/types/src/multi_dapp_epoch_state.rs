@@ -0,0 +1,193 @@
+use ethers::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use state_fold_types::ethers;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::EpochInitialState;
+
+/// Current on-disk layout version of [`MultiDappEpochState`]. Bump this and
+/// add a historical shadow struct plus an upgrade arm in [`migrate`] whenever
+/// the layout changes, mirroring [`crate::EPOCH_INITIAL_STATE_VERSION`].
+pub const MULTI_DAPP_EPOCH_STATE_VERSION: u16 = 1;
+
+/// Like [`EpochInitialState`], but tracks the current epoch of every DApp a
+/// node follows in a single snapshot, so one state-fold instance can serve a
+/// multi-tenant node instead of one instance per DApp.
+///
+/// The map is a `BTreeMap` rather than a `HashMap` so that iteration order is
+/// deterministic, which keeps `MultiDappEpochState` usable as a `Hash`/`Eq`
+/// state-fold key.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct MultiDappEpochState {
+    /// Layout version this value was constructed with; see [`migrate`].
+    pub version: u16,
+
+    pub epochs: BTreeMap<Address, U256>,
+}
+
+impl Default for MultiDappEpochState {
+    fn default() -> Self {
+        Self {
+            version: MULTI_DAPP_EPOCH_STATE_VERSION,
+            epochs: BTreeMap::new(),
+        }
+    }
+}
+
+impl MultiDappEpochState {
+    pub fn new(epochs: BTreeMap<Address, U256>) -> Self {
+        Self {
+            version: MULTI_DAPP_EPOCH_STATE_VERSION,
+            epochs,
+        }
+    }
+
+    /// The epoch number tracked for `dapp_contract_address`, if any.
+    pub fn epoch_number_of(&self, dapp_contract_address: &Address) -> Option<U256> {
+        self.epochs.get(dapp_contract_address).copied()
+    }
+}
+
+impl From<EpochInitialState> for MultiDappEpochState {
+    fn from(state: EpochInitialState) -> Self {
+        let mut epochs = BTreeMap::new();
+        epochs.insert(*state.dapp_contract_address, state.epoch_number);
+        Self::new(epochs)
+    }
+}
+
+/// Projects a single DApp's epoch out of a `MultiDappEpochState`. Returns
+/// `None` if `dapp_contract_address` isn't tracked in the snapshot. The L1
+/// anchor and beacon root are taken as arguments, since a bare
+/// `(Address, U256)` pair doesn't carry them.
+impl MultiDappEpochState {
+    pub fn to_epoch_initial_state(
+        &self,
+        dapp_contract_address: Arc<Address>,
+        l1_block_number: U256,
+        beacon_block_root: Option<[u8; 32]>,
+    ) -> Option<EpochInitialState> {
+        let epoch_number = self.epoch_number_of(&dapp_contract_address)?;
+        Some(EpochInitialState {
+            version: crate::EPOCH_INITIAL_STATE_VERSION,
+            dapp_contract_address,
+            epoch_number,
+            l1_block_number,
+            beacon_block_root,
+        })
+    }
+}
+
+/// Layout that predates the `version` discriminant: the original
+/// `MultiDappEpochState` shipped with only the `epochs` map.
+#[derive(Deserialize)]
+struct MultiDappEpochStateV0 {
+    epochs: BTreeMap<Address, U256>,
+}
+
+/// Just enough of the payload to read the `version` discriminant without
+/// committing to the rest of the current layout.
+#[derive(Deserialize)]
+struct VersionProbe {
+    #[serde(default)]
+    version: u16,
+}
+
+/// Deserializes a persisted `MultiDappEpochState`, upgrading older on-disk
+/// layouts to the current representation with sensible defaults. Payloads
+/// from before versioning was introduced (no `version` field) are treated as
+/// v0 and carried over as-is.
+pub fn migrate(raw: &[u8]) -> Result<MultiDappEpochState, serde_json::Error> {
+    let probe: VersionProbe = serde_json::from_slice(raw)?;
+
+    if probe.version >= MULTI_DAPP_EPOCH_STATE_VERSION {
+        return serde_json::from_slice(raw);
+    }
+
+    let v0: MultiDappEpochStateV0 = serde_json::from_slice(raw)?;
+    Ok(MultiDappEpochState {
+        version: MULTI_DAPP_EPOCH_STATE_VERSION,
+        epochs: v0.epochs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_from_single_dapp_state() {
+        let single = EpochInitialState {
+            version: crate::EPOCH_INITIAL_STATE_VERSION,
+            dapp_contract_address: Arc::new(Address::repeat_byte(0x11)),
+            epoch_number: U256::from(3),
+            l1_block_number: U256::from(100),
+            beacon_block_root: None,
+        };
+
+        let multi = MultiDappEpochState::from(single);
+        assert_eq!(
+            multi.epoch_number_of(&Address::repeat_byte(0x11)),
+            Some(U256::from(3))
+        );
+    }
+
+    #[test]
+    fn round_trips_back_to_single_dapp_state() {
+        let dapp_a = Address::repeat_byte(0xaa);
+        let dapp_b = Address::repeat_byte(0xbb);
+
+        let mut epochs = BTreeMap::new();
+        epochs.insert(dapp_a, U256::from(5));
+        epochs.insert(dapp_b, U256::from(9));
+        let multi = MultiDappEpochState::new(epochs);
+
+        let single = multi
+            .to_epoch_initial_state(Arc::new(dapp_b), U256::from(200), None)
+            .unwrap();
+
+        assert_eq!(single.dapp_contract_address, Arc::new(dapp_b));
+        assert_eq!(single.epoch_number, U256::from(9));
+
+        assert!(multi
+            .to_epoch_initial_state(Arc::new(Address::zero()), U256::zero(), None)
+            .is_none());
+    }
+
+    #[test]
+    fn iteration_order_is_deterministic() {
+        let mut epochs = BTreeMap::new();
+        epochs.insert(Address::repeat_byte(0x02), U256::from(1));
+        epochs.insert(Address::repeat_byte(0x01), U256::from(2));
+        let multi = MultiDappEpochState::new(epochs);
+
+        let ordered: Vec<Address> = multi.epochs.keys().copied().collect();
+        assert_eq!(
+            ordered,
+            vec![Address::repeat_byte(0x01), Address::repeat_byte(0x02)]
+        );
+    }
+
+    #[test]
+    fn round_trips_current_layout() {
+        let mut epochs = BTreeMap::new();
+        epochs.insert(Address::repeat_byte(0x01), U256::from(4));
+        let state = MultiDappEpochState::new(epochs);
+
+        let raw = serde_json::to_vec(&state).unwrap();
+        assert_eq!(migrate(&raw).unwrap(), state);
+    }
+
+    #[test]
+    fn migrates_v0_layout_with_defaults() {
+        let mut epochs = BTreeMap::new();
+        epochs.insert(Address::repeat_byte(0x01), U256::from(4));
+
+        let raw = serde_json::json!({ "epochs": epochs }).to_string().into_bytes();
+
+        let migrated = migrate(&raw).unwrap();
+        assert_eq!(migrated.version, MULTI_DAPP_EPOCH_STATE_VERSION);
+        assert_eq!(migrated.epoch_number_of(&Address::repeat_byte(0x01)), Some(U256::from(4)));
+    }
+}